@@ -1,3 +1,4 @@
+use super::hooks;
 use super::list;
 use super::raw_types::values::{ValueData, ValueTag};
 use super::string;
@@ -67,6 +68,23 @@ impl DMContext {
 		unsafe { Value::new(ValueTag::World, ValueData { id: 0 }) }
 	}
 
+	/// Calls the original implementation of the currently hooked proc, bypassing the hook.
+	/// Useful for hooks that wrap a proc instead of replacing it.
+	///
+	/// Will return a runtime if called outside of a hook.
+	///
+	/// # Example
+	/// ```ignore
+	/// #[hook("/proc/my_proc")]
+	/// fn my_proc_hook() {
+	///     let args: Vec<&Value> = args.iter().collect();
+	///     ctx.call_original(usr, src, &args)
+	/// }
+	/// ```
+	pub fn call_original(&self, usr: &Value, src: &Value, args: &[&Value]) -> DMResult {
+		hooks::call_original(usr, src, args)
+	}
+
 	pub unsafe fn new() -> Self {
 		// Pretty dumb way to set the lifetime but im not changing it now
 		Self {}
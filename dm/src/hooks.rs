@@ -3,12 +3,19 @@ use super::raw_types;
 use super::value::Value;
 use super::DMContext;
 use crate::raw_types::values::IntoRawValue;
+use crate::runtime;
 use crate::runtime::DMResult;
 use detour::RawDetour;
-use std::cell::RefCell;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::ffi::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
 
 #[doc(hidden)]
 pub struct CompileTimeHook {
@@ -57,6 +64,7 @@ pub enum HookFailure {
 	NotInitialized,
 	ProcNotFound,
 	AlreadyHooked,
+	NotHooked,
 	UnknownFailure,
 }
 
@@ -66,6 +74,7 @@ impl std::fmt::Debug for HookFailure {
 			Self::NotInitialized => write!(f, "Library not initialized"),
 			Self::ProcNotFound => write!(f, "Proc not found"),
 			Self::AlreadyHooked => write!(f, "Proc is already hooked"),
+			Self::NotHooked => write!(f, "Proc is not hooked"),
 			Self::UnknownFailure => write!(f, "Unknown failure"),
 		}
 	}
@@ -88,41 +97,392 @@ pub fn init() -> Result<(), String> {
 
 pub type ProcHook = fn(&DMContext, &Value, &Value, &mut Vec<Value>) -> DMResult;
 
+/// Called with the proc id and arguments of every proc call, hooked or not.
+pub type GlobalHook = fn(raw_types::procs::ProcId, &[Value]);
+
+/// Called with the error message when a hook fails.
+pub type HookErrorHandler = fn(&str);
+
+/// Called with a description of every attempt to register a hook and its outcome.
+pub type RegistrationLogger = fn(&str);
+
+// The parts of a hooked call that aren't exposed to the hook itself but are needed to forward it.
+#[derive(Copy, Clone)]
+struct HookFrame {
+	proc_type: u32,
+	proc_id: raw_types::procs::ProcId,
+	unknown1: u32,
+	unknown2: u32,
+	unknown3: u32,
+}
+
+struct HookEntry {
+	path: String,
+	hook: ProcHook,
+	enabled: AtomicBool,
+	calls: AtomicU64,
+}
+
+// Hooks are registered rarely and looked up on every proc call, so they live behind a process-wide RwLock.
+lazy_static! {
+	static ref PROC_HOOKS: RwLock<HashMap<raw_types::procs::ProcId, HookEntry>> =
+		RwLock::new(HashMap::new());
+	static ref HOOK_ERROR_HANDLER: RwLock<Option<HookErrorHandler>> = RwLock::new(None);
+	static ref REGISTRATION_LOGGER: RwLock<Option<RegistrationLogger>> = RwLock::new(None);
+}
+
+// Checked on every proc call, so it's a bare fn pointer (0 for none) rather than another lock.
+static GLOBAL_HOOK: AtomicUsize = AtomicUsize::new(0);
+
 thread_local! {
-	static PROC_HOOKS: RefCell<HashMap<raw_types::procs::ProcId, ProcHook>> = RefCell::new(HashMap::new());
+	static HOOK_FRAMES: RefCell<Vec<HookFrame>> = const { RefCell::new(Vec::new()) };
+	static IN_GLOBAL_HOOK: Cell<bool> = const { Cell::new(false) };
 }
 
-fn hook_by_id(id: raw_types::procs::ProcId, hook: ProcHook) -> Result<(), HookFailure> {
-	PROC_HOOKS.with(|h| {
-		let mut map = h.borrow_mut();
-		match map.entry(id) {
-			Entry::Vacant(v) => {
-				v.insert(hook);
-				Ok(())
-			}
-			Entry::Occupied(_) => Err(HookFailure::AlreadyHooked),
+fn hook_by_id(p: &Proc, hook: ProcHook) -> Result<(), HookFailure> {
+	match PROC_HOOKS.write().unwrap().entry(p.id) {
+		Entry::Vacant(v) => {
+			v.insert(HookEntry {
+				path: p.path.clone(),
+				hook,
+				enabled: AtomicBool::new(true),
+				calls: AtomicU64::new(0),
+			});
+			Ok(())
 		}
-	})
+		Entry::Occupied(_) => Err(HookFailure::AlreadyHooked),
+	}
 }
 
+fn log_registration(message: String) {
+	let logger = *REGISTRATION_LOGGER.read().unwrap();
+	if let Some(logger) = logger {
+		logger(&message);
+	}
+}
+
+fn hook_proc(p: &Proc, hook: ProcHook) -> Result<(), HookFailure> {
+	let result = hook_by_id(p, hook);
+	match &result {
+		Ok(()) => log_registration(format!("Hooked {} (proc id {})", p.path, p.id.0)),
+		Err(e) => log_registration(format!(
+			"Failed to hook {} (proc id {}): {:?}",
+			p.path, p.id.0, e
+		)),
+	}
+	result
+}
+
+/// Logs every hook registration attempt through `logger`, e.g. to find out why a hook never fires.
+pub fn set_registration_logger(logger: RegistrationLogger) {
+	*REGISTRATION_LOGGER.write().unwrap() = Some(logger);
+}
+
+/// Stops logging hook registrations through the logger set with [set_registration_logger].
+pub fn clear_registration_logger() {
+	*REGISTRATION_LOGGER.write().unwrap() = None;
+}
+
+/// Removes every hook, including the callback registered with [hook_all],
+/// and goes back to reporting hook errors through `stack_trace`.
+/// Each removed hook is logged before the registration logger is removed too.
 pub fn clear_hooks() {
-	PROC_HOOKS.with(|h| h.borrow_mut().clear());
+	let removed: Vec<_> = PROC_HOOKS.write().unwrap().drain().collect();
+	for (id, entry) in removed {
+		log_registration(format!("Unhooked {} (proc id {})", entry.path, id.0));
+	}
+	unhook_all();
+	clear_hook_error_handler();
+	clear_registration_logger();
+}
+
+/// Suspends or resumes a hook without removing it. While disabled, the original proc runs instead.
+pub fn set_hook_enabled(id: raw_types::procs::ProcId, enabled: bool) -> Result<(), HookFailure> {
+	match PROC_HOOKS.read().unwrap().get(&id) {
+		Some(entry) => {
+			entry.enabled.store(enabled, Ordering::Relaxed);
+			Ok(())
+		}
+		None => Err(HookFailure::NotHooked),
+	}
+}
+
+/// Returns the ids of all currently hooked procs.
+pub fn hooked_procs() -> Vec<raw_types::procs::ProcId> {
+	PROC_HOOKS.read().unwrap().keys().copied().collect()
+}
+
+/// Returns how many times each hooked proc has been called since it was hooked or the stats were last reset,
+/// including calls made while its hook was disabled.
+pub fn hook_stats() -> HashMap<raw_types::procs::ProcId, u64> {
+	PROC_HOOKS
+		.read()
+		.unwrap()
+		.iter()
+		.map(|(id, entry)| (*id, entry.calls.load(Ordering::Relaxed)))
+		.collect()
+}
+
+/// Resets the call counts returned by [hook_stats].
+pub fn reset_hook_stats() {
+	for entry in PROC_HOOKS.read().unwrap().values() {
+		entry.calls.store(0, Ordering::Relaxed);
+	}
+}
+
+/// Registers a callback that sees every proc call before it is dispatched, e.g. for profiling.
+/// It can't change the call's arguments or result. Registering again replaces the previous callback.
+pub fn hook_all(hook: GlobalHook) {
+	GLOBAL_HOOK.store(hook as usize, Ordering::Release);
+}
+
+/// Removes the callback registered with [hook_all].
+pub fn unhook_all() {
+	GLOBAL_HOOK.store(0, Ordering::Release);
+}
+
+/// Routes errors returned by hooks to `handler` instead of DM's `stack_trace`.
+pub fn set_hook_error_handler(handler: HookErrorHandler) {
+	*HOOK_ERROR_HANDLER.write().unwrap() = Some(handler);
+}
+
+/// Removes the handler set with [set_hook_error_handler], so errors go to `stack_trace` again.
+pub fn clear_hook_error_handler() {
+	*HOOK_ERROR_HANDLER.write().unwrap() = None;
+}
+
+// By default errors go to `src.stack_trace()`, or the global `/proc/stack_trace` for global procs.
+// Failing to report an error shouldn't take BYOND down with it, so any runtime from that is ignored.
+fn report_hook_error(src: &Value, message: &str) {
+	// This runs inside call_proc_by_id_hook, so a panicking handler (or from_string) mustn't unwind any further
+	let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+		let handler = *HOOK_ERROR_HANDLER.read().unwrap();
+		if let Some(handler) = handler {
+			handler(message);
+			return;
+		}
+
+		// BYOND strings can't hold NULs, and from_string panics on them
+		let message = Value::from_string(message.replace('\0', ""));
+		if src.value.tag == raw_types::values::ValueTag::Null {
+			if let Some(stack_trace) = Proc::find("/proc/stack_trace") {
+				let _ = stack_trace.call(&[&message]);
+			}
+		} else {
+			let _ = src.call("stack_trace", &[&message]);
+		}
+	}));
+}
+
+// Panics can't be allowed to unwind into BYOND, so they're turned into an error message instead.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+	let message = match payload.downcast::<String>() {
+		Ok(s) => *s,
+		Err(payload) => match payload.downcast::<&'static str>() {
+			Ok(s) => String::from(*s),
+			Err(_) => String::from("Unknown panic"),
+		},
+	};
+	format!("Hook panicked: {}", message)
 }
 
 pub fn hook<S: Into<String>>(name: S, hook: ProcHook) -> Result<(), HookFailure> {
-	match super::proc::get_proc(name) {
-		Some(p) => hook_by_id(p.id, hook),
-		None => Err(HookFailure::ProcNotFound),
+	let name = name.into();
+	match super::proc::get_proc(name.as_str()) {
+		Some(p) => hook_proc(&p, hook),
+		None => {
+			log_registration(format!(
+				"Failed to hook {}: {:?}",
+				name,
+				HookFailure::ProcNotFound
+			));
+			Err(HookFailure::ProcNotFound)
+		}
+	}
+}
+
+/// Calls the original implementation of the proc whose hook is currently running.
+///
+/// This goes straight to BYOND's original function, so the hook isn't re-entered.
+pub fn call_original(usr: &Value, src: &Value, args: &[&Value]) -> DMResult {
+	let frame = match HOOK_FRAMES.with(|f| f.borrow().last().copied()) {
+		Some(frame) => frame,
+		None => {
+			return Err(runtime::Runtime::new(
+				"call_original used outside of a hook",
+			))
+		}
+	};
+
+	unsafe {
+		// Increment ref-count of args permenently before passing them on
+		for v in args {
+			raw_types::funcs::inc_ref_count(v.value);
+		}
+
+		let mut args: Vec<_> = args.iter().map(|e| e.value).collect();
+
+		let result = call_proc_by_id_original_trampoline(
+			usr.value,
+			frame.proc_type,
+			frame.proc_id,
+			frame.unknown1,
+			src.value,
+			args.as_mut_ptr(),
+			args.len(),
+			frame.unknown2,
+			frame.unknown3,
+		);
+
+		Ok(Value::from_raw_owned(result))
+	}
+}
+
+// Matches `text` against a glob where `*` matches any run of characters except `/`,
+// `**` matches any run of characters including `/`, `**/` matches zero or more whole segments,
+// and `?` matches one character other than `/`.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+	enum Token {
+		Literal(u8),
+		AnyChar,
+		AnyInSegment,
+		AnyDepth,
+		AnySegments,
+	}
+
+	let pattern = pattern.as_bytes();
+	let text = text.as_bytes();
+
+	let mut tokens = Vec::new();
+	let mut i = 0;
+	while i < pattern.len() {
+		let (token, len) = match &pattern[i..] {
+			[b'*', b'*', b'/', ..] => (Token::AnySegments, 3),
+			[b'*', b'*', ..] => (Token::AnyDepth, 2),
+			[b'*', ..] => (Token::AnyInSegment, 1),
+			[b'?', ..] => (Token::AnyChar, 1),
+			[c, ..] => (Token::Literal(*c), 1),
+			[] => unreachable!(),
+		};
+		tokens.push(token);
+		i += len;
+	}
+
+	// Whether tokens[p..] matches text[t..], memoized so repeated wildcards stay polynomial
+	fn matches(
+		tokens: &[Token],
+		text: &[u8],
+		p: usize,
+		t: usize,
+		memo: &mut Vec<Option<bool>>,
+	) -> bool {
+		let key = p * (text.len() + 1) + t;
+		if let Some(result) = memo[key] {
+			return result;
+		}
+
+		let result = match tokens.get(p) {
+			None => t == text.len(),
+			Some(Token::Literal(c)) => {
+				text.get(t) == Some(c) && matches(tokens, text, p + 1, t + 1, memo)
+			}
+			Some(Token::AnyChar) => {
+				t < text.len() && text[t] != b'/' && matches(tokens, text, p + 1, t + 1, memo)
+			}
+			Some(Token::AnyInSegment) => {
+				matches(tokens, text, p + 1, t, memo)
+					|| (t < text.len() && text[t] != b'/' && matches(tokens, text, p, t + 1, memo))
+			}
+			Some(Token::AnyDepth) => {
+				matches(tokens, text, p + 1, t, memo)
+					|| (t < text.len() && matches(tokens, text, p, t + 1, memo))
+			}
+			Some(Token::AnySegments) => {
+				matches(tokens, text, p + 1, t, memo)
+					|| (t..text.len())
+						.filter(|&end| text[end] == b'/')
+						.any(|end| matches(tokens, text, p + 1, end + 1, memo))
+			}
+		};
+
+		memo[key] = Some(result);
+		result
+	}
+
+	let mut memo = vec![None; (tokens.len() + 1) * (text.len() + 1)];
+	matches(&tokens, text, 0, 0, &mut memo)
+}
+
+/// Hooks every proc whose path matches `pattern`, returning how many were hooked.
+///
+/// `*` matches any run of characters within one path segment, `**` matches across segments,
+/// `**/` matches zero or more whole segments and `?` matches a single character other than `/`.
+/// So `/proc/*` matches global procs only, `/mob/living/proc/*` matches procs defined on `/mob/living`,
+/// and `/mob/living/**` or `/mob/living/**/proc/Life` also match its subtypes' procs.
+/// Like with [hook], only the base proc of each match is hooked.
+///
+/// `/proc/` and `/verb/` are interchangeable here as everywhere else, so verbs are matched too:
+/// `/mob/living/proc/*` also hooks the verbs defined on `/mob/living`, and `/proc/*` also hooks global verbs.
+///
+/// Procs that are already hooked are skipped and not counted.
+/// Returns [HookFailure::ProcNotFound] if nothing matched at all.
+pub fn hook_pattern(pattern: &str, hook: ProcHook) -> Result<usize, HookFailure> {
+	// Proc paths are stored with `/proc/` stripped, which leaves global procs as the only ones with a single segment
+	let stripped = super::proc::strip_path(pattern.to_owned());
+	let procs = super::proc::find_procs(|path| glob_matches(&stripped, path));
+
+	if procs.is_empty() {
+		log_registration(format!(
+			"Failed to hook {}: {:?}",
+			pattern,
+			HookFailure::ProcNotFound
+		));
+		return Err(HookFailure::ProcNotFound);
+	}
+
+	let mut hooked = 0;
+	for p in procs {
+		match hook_proc(&p, hook) {
+			Ok(()) => hooked += 1,
+			Err(HookFailure::AlreadyHooked) => {}
+			Err(e) => return Err(e),
+		}
 	}
+	Ok(hooked)
 }
 
 impl Proc {
 	#[allow(unused)]
 	pub fn hook(&self, func: ProcHook) -> Result<(), HookFailure> {
-		hook_by_id(self.id, func)
+		hook_proc(self, func)
 	}
 }
 
+// Counts a call to `id` and returns its hook, if it has one that's enabled.
+fn lookup_hook(id: raw_types::procs::ProcId) -> Option<ProcHook> {
+	PROC_HOOKS.read().unwrap().get(&id).and_then(|entry| {
+		// Calls are counted even while the hook is disabled
+		entry.calls.fetch_add(1, Ordering::Relaxed);
+		Some(entry.hook).filter(|_| entry.enabled.load(Ordering::Relaxed))
+	})
+}
+
+// Runs `hook` with `frame` available to call_original, turning a panic into an error.
+fn run_hook(
+	hook: ProcHook,
+	frame: HookFrame,
+	ctx: &DMContext,
+	src: &Value,
+	usr: &Value,
+	args: &mut Vec<Value>,
+) -> DMResult {
+	HOOK_FRAMES.with(|f| f.borrow_mut().push(frame));
+	let result = panic::catch_unwind(AssertUnwindSafe(|| hook(ctx, src, usr, args)))
+		.unwrap_or_else(|payload| Err(runtime::Runtime::new(panic_message(payload))));
+	HOOK_FRAMES.with(|f| f.borrow_mut().pop());
+	result
+}
+
 #[no_mangle]
 extern "C" fn call_proc_by_id_hook(
 	usr_raw: raw_types::values::Value,
@@ -135,7 +495,29 @@ extern "C" fn call_proc_by_id_hook(
 	unknown2: u32,
 	unknown3: u32,
 ) -> raw_types::values::Value {
-	match PROC_HOOKS.with(|h| match h.borrow().get(&proc_id) {
+	let global_hook = match GLOBAL_HOOK.load(Ordering::Acquire) {
+		0 => None,
+		// Only ever set from a GlobalHook in hook_all
+		ptr => Some(unsafe { std::mem::transmute::<usize, GlobalHook>(ptr) }),
+	};
+
+	// Procs called from inside the global hook don't go back through it
+	if let Some(global_hook) = global_hook.filter(|_| !IN_GLOBAL_HOOK.with(|f| f.get())) {
+		let args: Vec<Value> = unsafe {
+			std::slice::from_raw_parts(args_ptr, num_args)
+				.iter()
+				.map(|v| Value::from_raw(*v))
+				.collect()
+		};
+		IN_GLOBAL_HOOK.with(|f| f.set(true));
+		// Reported while still flagged, as reporting calls procs that would otherwise run (and panic in) the global hook again
+		if let Err(payload) = panic::catch_unwind(|| global_hook(proc_id, &args)) {
+			report_hook_error(&Value::null(), &panic_message(payload));
+		}
+		IN_GLOBAL_HOOK.with(|f| f.set(false));
+	}
+
+	match lookup_hook(proc_id) {
 		Some(hook) => {
 			let ctx = unsafe { DMContext::new() };
 			let src;
@@ -153,26 +535,28 @@ extern "C" fn call_proc_by_id_hook(
 					.collect();
 			}
 
-			let result = hook(&ctx, &src, &usr, &mut args);
+			let frame = HookFrame {
+				proc_type,
+				proc_id,
+				unknown1,
+				unknown2,
+				unknown3,
+			};
 
-			match result {
+			match run_hook(hook, frame, &ctx, &src, &usr, &mut args) {
 				Ok(r) => {
 					let result_raw = unsafe { (&r).into_raw_value() };
 					// Stealing our reference out of the Value
 					std::mem::forget(r);
-					Some(result_raw)
+					result_raw
 				}
 				Err(e) => {
 					// TODO: Some info about the hook would be useful (as the hook is never part of byond's stack, the runtime won't show it.)
-					src.call("stack_trace", &[&Value::from_string(e.message.as_str())])
-						.unwrap();
-					unsafe { Some(Value::null().into_raw_value()) }
+					report_hook_error(&src, &e.message);
+					unsafe { Value::null().into_raw_value() }
 				}
 			}
 		}
-		None => None,
-	}) {
-		Some(result) => result,
 		None => unsafe {
 			call_proc_by_id_original_trampoline(
 				usr_raw, proc_type, proc_id, unknown1, src_raw, args_ptr, num_args, unknown2,
@@ -181,3 +565,238 @@ extern "C" fn call_proc_by_id_hook(
 		},
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		call_original, clear_registration_logger, glob_matches, hook, hook_proc, hook_stats,
+		lookup_hook, panic_message, reset_hook_stats, run_hook, set_hook_enabled,
+		set_registration_logger, HookFailure, HookFrame, ProcHook, HOOK_FRAMES,
+	};
+	use crate::proc::{strip_path, Proc};
+	use crate::raw_types::procs::ProcId;
+	use crate::{DMContext, DMResult, Value};
+	use lazy_static::lazy_static;
+	use std::cell::RefCell;
+	use std::collections::HashMap;
+	use std::hint::black_box;
+	use std::mem::ManuallyDrop;
+	use std::sync::Mutex;
+	use std::time::Instant;
+
+	lazy_static! {
+		static ref LOGGED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+		// reset_hook_stats resets every hook, so tests that check call counts take turns
+		static ref STATS: Mutex<()> = Mutex::new(());
+	}
+
+	fn never_called(_: &DMContext, _: &Value, _: &Value, _: &mut Vec<Value>) -> DMResult {
+		unreachable!()
+	}
+
+	// Hooks a proc that doesn't exist in BYOND, which is all lookup_hook needs.
+	// Tests run in parallel and share the hook table, so each one should use its own id.
+	fn hook_fake_proc(id: ProcId) {
+		let p = Proc {
+			id,
+			entry: std::ptr::null_mut(),
+			path: format!("/proc/fake_{}", id.0),
+		};
+		assert!(hook_proc(&p, never_called).is_ok());
+	}
+
+	#[test]
+	fn glob() {
+		assert!(glob_matches("/mob/living/*", "/mob/living/Life"));
+		assert!(!glob_matches("/mob/living/*", "/mob/living/carbon/Life"));
+		assert!(glob_matches("/mob/living/**", "/mob/living/carbon/Life"));
+		assert!(!glob_matches("/mob/living/*", "/mob/Life"));
+		assert!(glob_matches("/datum/proc_?", "/datum/proc_a"));
+		assert!(!glob_matches("/datum/proc_?", "/datum/proc_"));
+		assert!(glob_matches("/obj/*/attack*", "/obj/item/attack_self"));
+		assert!(!glob_matches("/obj/*/attack", "/obj/item/attack_self"));
+		assert!(glob_matches("/mob/**/Life", "/mob/Life"));
+		assert!(glob_matches("/mob/**/Life", "/mob/living/carbon/Life"));
+		assert!(!glob_matches("/mob/**/Life", "/mob/xLife"));
+	}
+
+	#[test]
+	fn glob_many_wildcards() {
+		// Exponential without memoization; this would take seconds
+		let path = format!("/{}", "a".repeat(59));
+		assert!(!glob_matches("**a**a**a**a**a**a**b", &path));
+		assert!(!glob_matches("*a*a*a*a*a*a*b", &path));
+	}
+
+	#[test]
+	fn global_proc_pattern_skips_type_procs() {
+		let global_procs = strip_path(String::from("/proc/*"));
+		assert!(glob_matches(
+			&global_procs,
+			&strip_path(String::from("/proc/do_explode"))
+		));
+		assert!(!glob_matches(
+			&global_procs,
+			&strip_path(String::from("/mob/living/proc/Life"))
+		));
+
+		let prefixed = strip_path(String::from("/proc/mob*"));
+		assert!(glob_matches(
+			&prefixed,
+			&strip_path(String::from("/proc/mob_count"))
+		));
+		assert!(!glob_matches(
+			&prefixed,
+			&strip_path(String::from("/mob/proc/Life"))
+		));
+	}
+
+	#[test]
+	fn proc_pattern_matches_verbs() {
+		let living_procs = strip_path(String::from("/mob/living/proc/*"));
+		assert!(glob_matches(
+			&living_procs,
+			&strip_path(String::from("/mob/living/verb/say"))
+		));
+		assert!(glob_matches(
+			&strip_path(String::from("/proc/*")),
+			&strip_path(String::from("/verb/ooc"))
+		));
+	}
+
+	#[test]
+	fn panic_messages() {
+		assert_eq!(
+			panic_message(Box::new(String::from("owned"))),
+			"Hook panicked: owned"
+		);
+		assert_eq!(panic_message(Box::new("static")), "Hook panicked: static");
+		assert_eq!(panic_message(Box::new(42)), "Hook panicked: Unknown panic");
+	}
+
+	#[test]
+	fn call_original_outside_hook_fails() {
+		// Dropping a Value calls into BYOND
+		let null = ManuallyDrop::new(Value::null());
+		assert!(call_original(&null, &null, &[]).is_err());
+	}
+
+	#[test]
+	fn panicking_hook_returns_error() {
+		fn panics(_: &DMContext, _: &Value, _: &Value, _: &mut Vec<Value>) -> DMResult {
+			panic!("boom")
+		}
+
+		let frame = HookFrame {
+			proc_type: 0,
+			proc_id: ProcId(u32::MAX),
+			unknown1: 0,
+			unknown2: 0,
+			unknown3: 0,
+		};
+		let ctx = unsafe { DMContext::new() };
+		let null = ManuallyDrop::new(Value::null());
+		let mut args = Vec::new();
+
+		match run_hook(panics, frame, &ctx, &null, &null, &mut args) {
+			Err(e) => assert_eq!(e.message, "Hook panicked: boom"),
+			Ok(r) => {
+				std::mem::forget(r);
+				panic!("panicking hook returned Ok")
+			}
+		}
+		assert!(HOOK_FRAMES.with(|f| f.borrow().is_empty()));
+		assert!(call_original(&null, &null, &[]).is_err());
+	}
+
+	#[test]
+	fn enabling_unhooked_proc_fails() {
+		assert!(matches!(
+			set_hook_enabled(ProcId(u32::MAX), false),
+			Err(HookFailure::NotHooked)
+		));
+	}
+
+	#[test]
+	fn counts_hooked_calls() {
+		let _stats = STATS.lock().unwrap();
+		let id = ProcId(u32::MAX - 1);
+		hook_fake_proc(id);
+
+		for _ in 0..5 {
+			assert!(lookup_hook(id).is_some());
+		}
+		assert_eq!(hook_stats()[&id], 5);
+
+		reset_hook_stats();
+		assert_eq!(hook_stats()[&id], 0);
+	}
+
+	// Compares the shared lookup against the thread_local table it replaced.
+	// Run with `cargo test --release -- --ignored --nocapture lookup_cost`.
+	#[test]
+	#[ignore]
+	fn lookup_cost() {
+		thread_local! {
+			static LOCAL_HOOKS: RefCell<HashMap<ProcId, ProcHook>> = RefCell::new(HashMap::new());
+		}
+
+		const LOOKUPS: u32 = 10_000_000;
+		let id = ProcId(u32::MAX - 3);
+		hook_fake_proc(id);
+		LOCAL_HOOKS.with(|h| h.borrow_mut().insert(id, never_called as ProcHook));
+
+		let start = Instant::now();
+		for _ in 0..LOOKUPS {
+			black_box(lookup_hook(black_box(id)));
+		}
+		let shared = start.elapsed();
+
+		let start = Instant::now();
+		for _ in 0..LOOKUPS {
+			black_box(LOCAL_HOOKS.with(|h| h.borrow().get(&black_box(id)).copied()));
+		}
+		let local = start.elapsed();
+
+		println!(
+			"{} lookups: RwLock + call count {:?} ({:.1}ns each), thread_local {:?} ({:.1}ns each)",
+			LOOKUPS,
+			shared,
+			shared.as_nanos() as f64 / LOOKUPS as f64,
+			local,
+			local.as_nanos() as f64 / LOOKUPS as f64,
+		);
+	}
+
+	#[test]
+	fn disabled_hooks_are_skipped_but_counted() {
+		let _stats = STATS.lock().unwrap();
+		let id = ProcId(u32::MAX - 2);
+		hook_fake_proc(id);
+
+		assert!(set_hook_enabled(id, false).is_ok());
+		assert!(lookup_hook(id).is_none());
+		assert_eq!(hook_stats()[&id], 1);
+
+		assert!(set_hook_enabled(id, true).is_ok());
+		assert!(lookup_hook(id).is_some());
+		assert_eq!(hook_stats()[&id], 2);
+	}
+
+	#[test]
+	fn logs_hooking_missing_proc() {
+		fn log(message: &str) {
+			LOGGED.lock().unwrap().push(message.to_owned());
+		}
+
+		set_registration_logger(log);
+		let result = hook("/proc/does_not_exist", never_called);
+		// The logger is process-wide, so it mustn't outlive this test
+		clear_registration_logger();
+
+		assert!(matches!(result, Err(HookFailure::ProcNotFound)));
+		assert!(LOGGED.lock().unwrap().contains(&String::from(
+			"Failed to hook /proc/does_not_exist: Proc not found"
+		)));
+	}
+}
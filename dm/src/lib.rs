@@ -22,7 +22,12 @@ use init::{get_init_level, set_init_level, RequiredInitLevel};
 pub use callback::Callback;
 pub use context::DMContext;
 pub use dm_impl::hook;
-pub use hooks::CompileTimeHook;
+pub use hooks::{
+	clear_hook_error_handler, clear_registration_logger, hook_all, hook_pattern, hook_stats,
+	hooked_procs, reset_hook_stats, set_hook_enabled, set_hook_error_handler,
+	set_registration_logger, unhook_all, CompileTimeHook, GlobalHook, HookErrorHandler,
+	HookFailure, RegistrationLogger,
+};
 pub use list::List;
 pub use proc::Proc;
 pub use runtime::{ConversionResult, DMResult, Runtime};
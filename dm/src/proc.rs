@@ -94,7 +94,7 @@ impl<'a> Proc {
 
 thread_local!(static PROCS_BY_NAME: RefCell<HashMap<String, Vec<Proc>>> = RefCell::new(HashMap::new()));
 
-fn strip_path(p: String) -> String {
+pub fn strip_path(p: String) -> String {
 	p.replace("/proc/", "/").replace("/verb/", "/")
 }
 
@@ -149,3 +149,15 @@ pub fn get_proc_override<S: Into<String>>(path: S, override_id: usize) -> Option
 pub fn get_proc<S: Into<String>>(path: S) -> Option<Proc> {
 	get_proc_override(path, 0)
 }
+
+/// Retrieves the 0th override of every proc whose path satisfies `predicate`.
+/// Paths are passed to `predicate` with `/proc/` and `/verb/` stripped, as with [get_proc].
+pub fn find_procs<F: Fn(&str) -> bool>(predicate: F) -> Vec<Proc> {
+	PROCS_BY_NAME.with(|h| {
+		h.borrow()
+			.iter()
+			.filter(|(path, _)| predicate(path))
+			.filter_map(|(_, overrides)| overrides.first().cloned())
+			.collect()
+	})
+}